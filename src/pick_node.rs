@@ -1,3 +1,6 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
 use std::str::FromStr;
 use syntax::ast::*;
 use syntax::codemap::{Span, BytePos};
@@ -8,10 +11,11 @@ use driver;
 use visit::Visit;
 
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct NodeInfo {
     pub id: NodeId,
     pub span: Span,
+    pub kind: NodeKind,
 }
 
 
@@ -19,17 +23,36 @@ struct PickVisitor {
     node_info: Option<NodeInfo>,
     kind: NodeKind,
     target: Span,
+
+    // Stack of ancestors of the node currently being visited, used to build the path returned by
+    // `pick_node_path`.
+    stack: Vec<NodeInfo>,
+    path: Option<Vec<NodeInfo>>,
+}
+
+impl PickVisitor {
+    /// Record `info` as the match, snapshotting the current ancestor stack (including `info`
+    /// itself) as the path to it.
+    fn found(&mut self, info: NodeInfo) {
+        self.stack.push(info.clone());
+        self.path = Some(self.stack.clone());
+        self.stack.pop();
+        self.node_info = Some(info);
+    }
 }
 
 impl<'a> Visitor<'a> for PickVisitor {
     fn visit_item(&mut self, x: &'a Item) {
+        let info = NodeInfo { id: x.id, span: x.span, kind: NodeKind::Item };
+        self.stack.push(info.clone());
         // Recurse first, so that the deepest node gets visited first.  This way we get
         // the function and not its containing module, for example.
         visit::walk_item(self, x);
         if self.node_info.is_none() &&
            self.kind.includes(NodeKind::Item) &&
            x.span.contains(self.target) {
-            self.node_info = Some(NodeInfo { id: x.id, span: x.span });
+            self.path = Some(self.stack.clone());
+            self.node_info = Some(info.clone());
         }
 
         // Special case for modules.  If the cursor lies within the inner span of a mod item
@@ -38,73 +61,192 @@ impl<'a> Visitor<'a> for PickVisitor {
         if self.node_info.is_none() {
             if let ItemKind::Mod(ref m) = x.node {
                 if m.inner.contains(self.target) {
-                    self.node_info = Some(NodeInfo { id: x.id, span: x.span });
+                    self.path = Some(self.stack.clone());
+                    self.node_info = Some(info.clone());
                 }
             }
         }
+        self.stack.pop();
     }
 
     fn visit_trait_item(&mut self, x: &'a TraitItem) {
+        let info = NodeInfo { id: x.id, span: x.span, kind: NodeKind::TraitItem };
+        self.stack.push(info.clone());
         visit::walk_trait_item(self, x);
         if self.node_info.is_none() &&
            self.kind.includes(NodeKind::TraitItem) &&
            x.span.contains(self.target) {
-            self.node_info = Some(NodeInfo { id: x.id, span: x.span });
+            self.path = Some(self.stack.clone());
+            self.node_info = Some(info);
         }
+        self.stack.pop();
     }
 
     fn visit_impl_item(&mut self, x: &'a ImplItem) {
+        let info = NodeInfo { id: x.id, span: x.span, kind: NodeKind::ImplItem };
+        self.stack.push(info.clone());
         visit::walk_impl_item(self, x);
         if self.node_info.is_none() &&
            self.kind.includes(NodeKind::ImplItem) &&
            x.span.contains(self.target) {
-            self.node_info = Some(NodeInfo { id: x.id, span: x.span });
+            self.path = Some(self.stack.clone());
+            self.node_info = Some(info);
         }
+        self.stack.pop();
     }
 
     fn visit_foreign_item(&mut self, x: &'a ForeignItem) {
+        let info = NodeInfo { id: x.id, span: x.span, kind: NodeKind::ForeignItem };
+        self.stack.push(info.clone());
         visit::walk_foreign_item(self, x);
         if self.node_info.is_none() &&
            self.kind.includes(NodeKind::ForeignItem) &&
            x.span.contains(self.target) {
-            self.node_info = Some(NodeInfo { id: x.id, span: x.span });
+            self.path = Some(self.stack.clone());
+            self.node_info = Some(info);
         }
+        self.stack.pop();
     }
 
     fn visit_stmt(&mut self, x: &'a Stmt) {
+        let info = NodeInfo { id: x.id, span: x.span, kind: NodeKind::Stmt };
+        self.stack.push(info.clone());
         visit::walk_stmt(self, x);
         if self.node_info.is_none() &&
            self.kind.includes(NodeKind::Stmt) &&
            x.span.contains(self.target) {
-            self.node_info = Some(NodeInfo { id: x.id, span: x.span });
+            self.path = Some(self.stack.clone());
+            self.node_info = Some(info);
         }
+        self.stack.pop();
     }
 
     fn visit_expr(&mut self, x: &'a Expr) {
+        let info = NodeInfo { id: x.id, span: x.span, kind: NodeKind::Expr };
+        self.stack.push(info.clone());
         visit::walk_expr(self, x);
+
+        // Loop/break/continue labels aren't walked as a separate node kind by `Visitor`, so we
+        // check them here, before the enclosing expr, so that the label (if present) wins.
+        if self.node_info.is_none() && self.kind.includes(NodeKind::Label) {
+            let label = match x.node {
+                ExprKind::While(_, _, label) |
+                ExprKind::WhileLet(_, _, _, label) |
+                ExprKind::ForLoop(_, _, _, label) |
+                ExprKind::Loop(_, label) |
+                ExprKind::Break(label, _) |
+                ExprKind::Continue(label) => label,
+                _ => None,
+            };
+            if let Some(label) = label {
+                if label.ident.span.contains(self.target) {
+                    self.found(NodeInfo {
+                        id: x.id,
+                        span: label.ident.span,
+                        kind: NodeKind::Label,
+                    });
+                }
+            }
+        }
+
         if self.node_info.is_none() &&
            self.kind.includes(NodeKind::Expr) &&
            x.span.contains(self.target) {
-            self.node_info = Some(NodeInfo { id: x.id, span: x.span });
+            self.path = Some(self.stack.clone());
+            self.node_info = Some(info);
         }
+        self.stack.pop();
+    }
+
+    fn visit_struct_field(&mut self, x: &'a StructField) {
+        let info = NodeInfo { id: x.id, span: x.span, kind: NodeKind::StructField };
+        self.stack.push(info.clone());
+        visit::walk_struct_field(self, x);
+        if self.node_info.is_none() &&
+           self.kind.includes(NodeKind::StructField) &&
+           x.span.contains(self.target) {
+            self.path = Some(self.stack.clone());
+            self.node_info = Some(info);
+        }
+        self.stack.pop();
+    }
+
+    fn visit_variant(&mut self, v: &'a Variant, g: &'a Generics, item_id: NodeId) {
+        let info = NodeInfo { id: v.node.data.id(), span: v.span, kind: NodeKind::Variant };
+        self.stack.push(info.clone());
+        visit::walk_variant(self, v, g, item_id);
+        if self.node_info.is_none() &&
+           self.kind.includes(NodeKind::Variant) &&
+           v.span.contains(self.target) {
+            self.path = Some(self.stack.clone());
+            self.node_info = Some(info);
+        }
+        self.stack.pop();
+    }
+
+    fn visit_generic_param(&mut self, param: &'a GenericParam) {
+        visit::walk_generic_param(self, param);
+        if self.node_info.is_none() &&
+           self.kind.includes(NodeKind::GenericParam) &&
+           param.ident.span.contains(self.target) {
+            self.found(NodeInfo {
+                id: param.id,
+                span: param.ident.span,
+                kind: NodeKind::GenericParam,
+            });
+        }
+    }
+
+    fn visit_lifetime(&mut self, lifetime: &'a Lifetime) {
+        visit::walk_lifetime(self, lifetime);
+        if self.node_info.is_none() &&
+           self.kind.includes(NodeKind::Lifetime) &&
+           lifetime.ident.span.contains(self.target) {
+            self.found(NodeInfo {
+                id: lifetime.id,
+                span: lifetime.ident.span,
+                kind: NodeKind::Lifetime,
+            });
+        }
+    }
+
+    fn visit_block(&mut self, b: &'a Block) {
+        let info = NodeInfo { id: b.id, span: b.span, kind: NodeKind::Block };
+        self.stack.push(info.clone());
+        visit::walk_block(self, b);
+        if self.node_info.is_none() &&
+           self.kind.includes(NodeKind::Block) &&
+           b.span.contains(self.target) {
+            self.path = Some(self.stack.clone());
+            self.node_info = Some(info);
+        }
+        self.stack.pop();
     }
 
     fn visit_pat(&mut self, x: &'a Pat) {
+        let info = NodeInfo { id: x.id, span: x.span, kind: NodeKind::Pat };
+        self.stack.push(info.clone());
         visit::walk_pat(self, x);
         if self.node_info.is_none() &&
            self.kind.includes(NodeKind::Pat) &&
            x.span.contains(self.target) {
-            self.node_info = Some(NodeInfo { id: x.id, span: x.span });
+            self.path = Some(self.stack.clone());
+            self.node_info = Some(info);
         }
+        self.stack.pop();
     }
 
     fn visit_ty(&mut self, x: &'a Ty) {
+        let info = NodeInfo { id: x.id, span: x.span, kind: NodeKind::Ty };
+        self.stack.push(info.clone());
         visit::walk_ty(self, x);
         if self.node_info.is_none() &&
            self.kind.includes(NodeKind::Ty) &&
            x.span.contains(self.target) {
-            self.node_info = Some(NodeInfo { id: x.id, span: x.span });
+            self.path = Some(self.stack.clone());
+            self.node_info = Some(info);
         }
+        self.stack.pop();
     }
 
     // There's no `visit_arg`, unfortunately, so we have to do this instead.
@@ -118,9 +260,316 @@ impl<'a> Visitor<'a> for PickVisitor {
                    arg.pat.span.contains(self.target) ||
                    (arg.ty.span.ctxt == arg.pat.span.ctxt &&
                     arg.ty.span.between(arg.pat.span).contains(self.target)) {
+                    self.found(NodeInfo {
+                        id: arg.id,
+                        span: arg.ty.span.to(arg.pat.span),
+                        kind: NodeKind::Arg,
+                    });
+                } else if arg.ty.span.ctxt == arg.pat.span.ctxt &&
+                          arg.ty.span.to(arg.pat.span).contains(self.target) {
+                    // A range target spanning both the pattern and the type.
+                    self.found(NodeInfo {
+                        id: arg.id,
+                        span: arg.ty.span.to(arg.pat.span),
+                        kind: NodeKind::Arg,
+                    });
+                }
+            }
+        }
+    }
+
+    fn visit_mac(&mut self, mac: &'a Mac) {
+        visit::walk_mac(self, mac);
+    }
+}
+
+
+/// Like `PickVisitor`, but instead of finding the smallest node that *contains* `target`, finds
+/// every node of the requested `NodeKind` that is *contained in* `target`.
+struct PickContainedVisitor {
+    node_infos: Vec<NodeInfo>,
+    kind: NodeKind,
+    target: Span,
+}
+
+impl<'a> Visitor<'a> for PickContainedVisitor {
+    fn visit_item(&mut self, x: &'a Item) {
+        visit::walk_item(self, x);
+        if self.kind.includes(NodeKind::Item) && self.target.contains(x.span) {
+            self.node_infos.push(NodeInfo { id: x.id, span: x.span, kind: NodeKind::Item });
+        }
+    }
+
+    fn visit_trait_item(&mut self, x: &'a TraitItem) {
+        visit::walk_trait_item(self, x);
+        if self.kind.includes(NodeKind::TraitItem) && self.target.contains(x.span) {
+            self.node_infos.push(NodeInfo { id: x.id, span: x.span, kind: NodeKind::TraitItem });
+        }
+    }
+
+    fn visit_impl_item(&mut self, x: &'a ImplItem) {
+        visit::walk_impl_item(self, x);
+        if self.kind.includes(NodeKind::ImplItem) && self.target.contains(x.span) {
+            self.node_infos.push(NodeInfo { id: x.id, span: x.span, kind: NodeKind::ImplItem });
+        }
+    }
+
+    fn visit_foreign_item(&mut self, x: &'a ForeignItem) {
+        visit::walk_foreign_item(self, x);
+        if self.kind.includes(NodeKind::ForeignItem) && self.target.contains(x.span) {
+            self.node_infos.push(NodeInfo { id: x.id, span: x.span, kind: NodeKind::ForeignItem });
+        }
+    }
+
+    fn visit_stmt(&mut self, x: &'a Stmt) {
+        visit::walk_stmt(self, x);
+        if self.kind.includes(NodeKind::Stmt) && self.target.contains(x.span) {
+            self.node_infos.push(NodeInfo { id: x.id, span: x.span, kind: NodeKind::Stmt });
+        }
+    }
+
+    fn visit_expr(&mut self, x: &'a Expr) {
+        visit::walk_expr(self, x);
+
+        // Loop/break/continue labels aren't walked as a separate node kind by `Visitor`, so we
+        // check them here, as `PickVisitor::visit_expr` does.
+        if self.kind.includes(NodeKind::Label) {
+            let label = match x.node {
+                ExprKind::While(_, _, label) |
+                ExprKind::WhileLet(_, _, _, label) |
+                ExprKind::ForLoop(_, _, _, label) |
+                ExprKind::Loop(_, label) |
+                ExprKind::Break(label, _) |
+                ExprKind::Continue(label) => label,
+                _ => None,
+            };
+            if let Some(label) = label {
+                if self.target.contains(label.ident.span) {
+                    self.node_infos.push(NodeInfo { id: x.id, span: label.ident.span, kind: NodeKind::Label });
+                }
+            }
+        }
+
+        if self.kind.includes(NodeKind::Expr) && self.target.contains(x.span) {
+            self.node_infos.push(NodeInfo { id: x.id, span: x.span, kind: NodeKind::Expr });
+        }
+    }
+
+    fn visit_struct_field(&mut self, x: &'a StructField) {
+        visit::walk_struct_field(self, x);
+        if self.kind.includes(NodeKind::StructField) && self.target.contains(x.span) {
+            self.node_infos.push(NodeInfo { id: x.id, span: x.span, kind: NodeKind::StructField });
+        }
+    }
+
+    fn visit_variant(&mut self, v: &'a Variant, g: &'a Generics, item_id: NodeId) {
+        visit::walk_variant(self, v, g, item_id);
+        if self.kind.includes(NodeKind::Variant) && self.target.contains(v.span) {
+            self.node_infos.push(NodeInfo { id: v.node.data.id(), span: v.span, kind: NodeKind::Variant });
+        }
+    }
+
+    fn visit_generic_param(&mut self, param: &'a GenericParam) {
+        visit::walk_generic_param(self, param);
+        if self.kind.includes(NodeKind::GenericParam) && self.target.contains(param.ident.span) {
+            self.node_infos.push(NodeInfo { id: param.id, span: param.ident.span, kind: NodeKind::GenericParam });
+        }
+    }
+
+    fn visit_lifetime(&mut self, lifetime: &'a Lifetime) {
+        visit::walk_lifetime(self, lifetime);
+        if self.kind.includes(NodeKind::Lifetime) && self.target.contains(lifetime.ident.span) {
+            self.node_infos.push(NodeInfo { id: lifetime.id, span: lifetime.ident.span, kind: NodeKind::Lifetime });
+        }
+    }
+
+    fn visit_block(&mut self, b: &'a Block) {
+        visit::walk_block(self, b);
+        if self.kind.includes(NodeKind::Block) && self.target.contains(b.span) {
+            self.node_infos.push(NodeInfo { id: b.id, span: b.span, kind: NodeKind::Block });
+        }
+    }
+
+    fn visit_pat(&mut self, x: &'a Pat) {
+        visit::walk_pat(self, x);
+        if self.kind.includes(NodeKind::Pat) && self.target.contains(x.span) {
+            self.node_infos.push(NodeInfo { id: x.id, span: x.span, kind: NodeKind::Pat });
+        }
+    }
+
+    fn visit_ty(&mut self, x: &'a Ty) {
+        visit::walk_ty(self, x);
+        if self.kind.includes(NodeKind::Ty) && self.target.contains(x.span) {
+            self.node_infos.push(NodeInfo { id: x.id, span: x.span, kind: NodeKind::Ty });
+        }
+    }
+
+    fn visit_fn(&mut self, fk: FnKind<'a>, fd: &'a FnDecl, s: Span, _id: NodeId) {
+        visit::walk_fn(self, fk, fd, s);
+
+        if self.kind.includes(NodeKind::Arg) {
+            for arg in &fd.inputs {
+                let arg_span = arg.ty.span.to(arg.pat.span);
+                if self.target.contains(arg_span) {
+                    self.node_infos.push(NodeInfo { id: arg.id, span: arg_span, kind: NodeKind::Arg });
+                }
+            }
+        }
+    }
+
+    fn visit_mac(&mut self, mac: &'a Mac) {
+        visit::walk_mac(self, mac);
+    }
+}
+
+
+/// The inverse of `PickVisitor`: given a `NodeId`, finds that node's current span.  Matches
+/// across every node category `PickVisitor` handles (items, trait/impl/foreign items, stmts,
+/// exprs, pats, tys, fn args, struct fields, variants, generic params, lifetimes, blocks),
+/// short-circuiting as soon as the id is found.
+struct FindByIdVisitor {
+    node_info: Option<NodeInfo>,
+    target_id: NodeId,
+}
+
+impl<'a> Visitor<'a> for FindByIdVisitor {
+    fn visit_item(&mut self, x: &'a Item) {
+        if self.node_info.is_none() {
+            visit::walk_item(self, x);
+        }
+        if self.node_info.is_none() && x.id == self.target_id {
+            self.node_info = Some(NodeInfo { id: x.id, span: x.span, kind: NodeKind::Item });
+        }
+    }
+
+    fn visit_trait_item(&mut self, x: &'a TraitItem) {
+        if self.node_info.is_none() {
+            visit::walk_trait_item(self, x);
+        }
+        if self.node_info.is_none() && x.id == self.target_id {
+            self.node_info = Some(NodeInfo { id: x.id, span: x.span, kind: NodeKind::TraitItem });
+        }
+    }
+
+    fn visit_impl_item(&mut self, x: &'a ImplItem) {
+        if self.node_info.is_none() {
+            visit::walk_impl_item(self, x);
+        }
+        if self.node_info.is_none() && x.id == self.target_id {
+            self.node_info = Some(NodeInfo { id: x.id, span: x.span, kind: NodeKind::ImplItem });
+        }
+    }
+
+    fn visit_foreign_item(&mut self, x: &'a ForeignItem) {
+        if self.node_info.is_none() {
+            visit::walk_foreign_item(self, x);
+        }
+        if self.node_info.is_none() && x.id == self.target_id {
+            self.node_info = Some(NodeInfo { id: x.id, span: x.span, kind: NodeKind::ForeignItem });
+        }
+    }
+
+    fn visit_stmt(&mut self, x: &'a Stmt) {
+        if self.node_info.is_none() {
+            visit::walk_stmt(self, x);
+        }
+        if self.node_info.is_none() && x.id == self.target_id {
+            self.node_info = Some(NodeInfo { id: x.id, span: x.span, kind: NodeKind::Stmt });
+        }
+    }
+
+    fn visit_expr(&mut self, x: &'a Expr) {
+        if self.node_info.is_none() {
+            visit::walk_expr(self, x);
+        }
+        if self.node_info.is_none() && x.id == self.target_id {
+            self.node_info = Some(NodeInfo { id: x.id, span: x.span, kind: NodeKind::Expr });
+        }
+    }
+
+    fn visit_pat(&mut self, x: &'a Pat) {
+        if self.node_info.is_none() {
+            visit::walk_pat(self, x);
+        }
+        if self.node_info.is_none() && x.id == self.target_id {
+            self.node_info = Some(NodeInfo { id: x.id, span: x.span, kind: NodeKind::Pat });
+        }
+    }
+
+    fn visit_ty(&mut self, x: &'a Ty) {
+        if self.node_info.is_none() {
+            visit::walk_ty(self, x);
+        }
+        if self.node_info.is_none() && x.id == self.target_id {
+            self.node_info = Some(NodeInfo { id: x.id, span: x.span, kind: NodeKind::Ty });
+        }
+    }
+
+    fn visit_struct_field(&mut self, x: &'a StructField) {
+        if self.node_info.is_none() {
+            visit::walk_struct_field(self, x);
+        }
+        if self.node_info.is_none() && x.id == self.target_id {
+            self.node_info = Some(NodeInfo { id: x.id, span: x.span, kind: NodeKind::StructField });
+        }
+    }
+
+    fn visit_variant(&mut self, v: &'a Variant, g: &'a Generics, item_id: NodeId) {
+        if self.node_info.is_none() {
+            visit::walk_variant(self, v, g, item_id);
+        }
+        if self.node_info.is_none() && v.node.data.id() == self.target_id {
+            self.node_info = Some(NodeInfo { id: v.node.data.id(), span: v.span, kind: NodeKind::Variant });
+        }
+    }
+
+    fn visit_generic_param(&mut self, param: &'a GenericParam) {
+        if self.node_info.is_none() {
+            visit::walk_generic_param(self, param);
+        }
+        if self.node_info.is_none() && param.id == self.target_id {
+            self.node_info = Some(NodeInfo {
+                id: param.id,
+                span: param.ident.span,
+                kind: NodeKind::GenericParam,
+            });
+        }
+    }
+
+    fn visit_lifetime(&mut self, lifetime: &'a Lifetime) {
+        if self.node_info.is_none() {
+            visit::walk_lifetime(self, lifetime);
+        }
+        if self.node_info.is_none() && lifetime.id == self.target_id {
+            self.node_info = Some(NodeInfo {
+                id: lifetime.id,
+                span: lifetime.ident.span,
+                kind: NodeKind::Lifetime,
+            });
+        }
+    }
+
+    fn visit_block(&mut self, b: &'a Block) {
+        if self.node_info.is_none() {
+            visit::walk_block(self, b);
+        }
+        if self.node_info.is_none() && b.id == self.target_id {
+            self.node_info = Some(NodeInfo { id: b.id, span: b.span, kind: NodeKind::Block });
+        }
+    }
+
+    fn visit_fn(&mut self, fk: FnKind<'a>, fd: &'a FnDecl, s: Span, _id: NodeId) {
+        if self.node_info.is_none() {
+            visit::walk_fn(self, fk, fd, s);
+        }
+
+        if self.node_info.is_none() {
+            for arg in &fd.inputs {
+                if arg.id == self.target_id {
                     self.node_info = Some(NodeInfo {
                         id: arg.id,
                         span: arg.ty.span.to(arg.pat.span),
+                        kind: NodeKind::Arg,
                     });
                 }
             }
@@ -147,6 +596,13 @@ pub enum NodeKind {
     Pat,
     Ty,
     Arg,
+
+    StructField,
+    Variant,
+    GenericParam,
+    Lifetime,
+    Block,
+    Label,
 }
 
 impl NodeKind {
@@ -177,6 +633,13 @@ impl NodeKind {
             NodeKind::Pat => "pat",
             NodeKind::Ty => "ty",
             NodeKind::Arg => "arg",
+
+            NodeKind::StructField => "struct_field",
+            NodeKind::Variant => "variant",
+            NodeKind::GenericParam => "generic_param",
+            NodeKind::Lifetime => "lifetime",
+            NodeKind::Block => "block",
+            NodeKind::Label => "label",
         }
     }
 }
@@ -199,33 +662,215 @@ impl FromStr for NodeKind {
                 "ty" => NodeKind::Ty,
                 "arg" => NodeKind::Arg,
 
+                "struct_field" => NodeKind::StructField,
+                "variant" => NodeKind::Variant,
+                "generic_param" => NodeKind::GenericParam,
+                "lifetime" => NodeKind::Lifetime,
+                "block" => NodeKind::Block,
+                "label" => NodeKind::Label,
+
                 _ => return Err(()),
             };
         Ok(kind)
     }
 }
 
-pub fn pick_node(krate: &Crate, kind: NodeKind, pos: BytePos) -> Option<NodeInfo> {
+fn run_pick(krate: &Crate, kind: NodeKind, target: Span) -> PickVisitor {
     let mut v = PickVisitor {
         node_info: None,
         kind: kind,
-        target: Span { lo: pos, hi: pos, ctxt: SyntaxContext::empty() },
+        target: target,
+        stack: vec![NodeInfo { id: CRATE_NODE_ID, span: krate.span, kind: NodeKind::Item }],
+        path: None,
     };
     krate.visit(&mut v);
 
     // If the cursor falls inside the crate's module, then mark the crate itself.
     if v.node_info.is_none() {
         if krate.module.inner.contains(v.target) {
-            v.node_info = Some(NodeInfo { id: CRATE_NODE_ID, span: krate.span });
+            let info = NodeInfo { id: CRATE_NODE_ID, span: krate.span, kind: NodeKind::Item };
+            v.path = Some(vec![info.clone()]);
+            v.node_info = Some(info);
         }
     }
 
+    v
+}
+
+pub fn pick_node(krate: &Crate, kind: NodeKind, pos: BytePos) -> Option<NodeInfo> {
+    run_pick(krate, kind, Span { lo: pos, hi: pos, ctxt: SyntaxContext::empty() }).node_info
+}
+
+/// Find the smallest node of kind `kind` whose span fully contains `[lo, hi)`.  Useful for
+/// resolving an editor selection (as opposed to `pick_node`'s single-point cursor) to a node.
+pub fn pick_node_in_range(krate: &Crate,
+                          kind: NodeKind,
+                          lo: BytePos,
+                          hi: BytePos) -> Option<NodeInfo> {
+    run_pick(krate, kind, Span { lo: lo, hi: hi, ctxt: SyntaxContext::empty() }).node_info
+}
+
+/// Like `pick_node`, but returns the full ancestor chain of the picked node instead of just the
+/// node itself, ordered from the crate root down to the picked node.  Useful for breadcrumb UIs
+/// and for refactorings that need to know a picked node's enclosing item/impl/module.
+pub fn pick_node_path(krate: &Crate, kind: NodeKind, pos: BytePos) -> Option<Vec<NodeInfo>> {
+    run_pick(krate, kind, Span { lo: pos, hi: pos, ctxt: SyntaxContext::empty() }).path
+}
+
+/// The inverse of `pick_node`: given a `NodeId`, look up its current span.  Useful for
+/// refactoring passes that hold a `NodeId` from a prior pick or from analysis and need to
+/// re-anchor an edit or report a location for it.
+///
+/// Can't round-trip `NodeKind::Label`: a loop/break/continue label has no `NodeId` of its own
+/// (`pick_node` reports it under its enclosing expr's id, see `PickVisitor::visit_expr`), so a
+/// label's id is indistinguishable here from its enclosing `Expr`'s, and this always resolves it
+/// back to the `Expr`.
+pub fn find_span_by_id(krate: &Crate, id: NodeId) -> Option<NodeInfo> {
+    let mut v = FindByIdVisitor {
+        node_info: None,
+        target_id: id,
+    };
+    krate.visit(&mut v);
+
+    if v.node_info.is_none() && id == CRATE_NODE_ID {
+        v.node_info = Some(NodeInfo { id: CRATE_NODE_ID, span: krate.span, kind: NodeKind::Item });
+    }
+
     v.node_info
 }
 
+/// Find every node of kind `kind` that lies entirely within `[lo, hi)`.  Useful for identifying
+/// the exact statements (or other nodes) an editor selection should extract.
+///
+/// Unlike `col_to_byte_offset` and `find_path_to`, this (and the rest of the `Visitor` impls in
+/// this file) has no regression tests: exercising it needs a real `Crate` AST, and this crate has
+/// no harness for parsing source into one without the driver's full session/codemap setup.
+pub fn pick_nodes_contained_in_range(krate: &Crate,
+                                     kind: NodeKind,
+                                     lo: BytePos,
+                                     hi: BytePos) -> Vec<NodeInfo> {
+    let mut v = PickContainedVisitor {
+        node_infos: Vec::new(),
+        kind: kind,
+        target: Span { lo: lo, hi: hi, ctxt: SyntaxContext::empty() },
+    };
+    krate.visit(&mut v);
+    v.node_infos
+}
+
+/// The units a caller-supplied column number is measured in.  Editors and LSP clients disagree
+/// about this: LSP itself counts in UTF-16 code units, while some clients instead use Unicode
+/// scalar values (chars) or raw bytes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColumnEncoding {
+    /// Column is a byte offset into the line.
+    Utf8,
+    /// Column is a count of UTF-16 code units, as used by the Language Server Protocol.
+    Utf16,
+    /// Column is a count of Unicode scalar values (`char`s).
+    Utf32,
+}
+
+/// Core of `line_col_to_byte_pos`: find the byte offset within `line_src` (the line's text, with
+/// any trailing newline already stripped) that `col` (measured in `encoding` units) points into.
+/// Returns `None` if `col` is past the end of the line.  Split out as a pure function, independent
+/// of `FileMap`, so the column math can be unit-tested directly.
+fn col_to_byte_offset(line_src: &str, encoding: ColumnEncoding, col: u32) -> Option<usize> {
+    let mut byte_off = 0;
+    let mut unit_count = 0;
+    if unit_count >= col {
+        return Some(byte_off);
+    }
+    for ch in line_src.chars() {
+        let char_units = match encoding {
+            ColumnEncoding::Utf8 => ch.len_utf8() as u32,
+            ColumnEncoding::Utf16 => ch.len_utf16() as u32,
+            ColumnEncoding::Utf32 => 1,
+        };
+        // `col` may land inside a multi-unit char (e.g. UTF-16 col 1 pointing at the low
+        // surrogate of an astral character); clamp to the char's start rather than advancing
+        // past the whole char, so the cursor resolves to the char it's actually inside of.
+        if unit_count + char_units > col {
+            return Some(byte_off);
+        }
+        byte_off += ch.len_utf8();
+        unit_count += char_units;
+    }
+
+    if col > unit_count {
+        None
+    } else {
+        Some(byte_off)
+    }
+}
+
+/// Convert a `(line, col)` position, with `col` measured in `encoding` units, into the `BytePos`
+/// of the corresponding byte in `fm`'s source text.
+fn line_col_to_byte_pos(fm: &syntax::codemap::FileMap,
+                        encoding: ColumnEncoding,
+                        line: u32,
+                        col: u32) -> BytePos {
+    let (lo, hi) = fm.line_bounds(line as usize - 1);
+
+    let full_line_src = match fm.src {
+        Some(ref src) => &src[(lo.0 - fm.start_pos.0) as usize..(hi.0 - fm.start_pos.0) as usize],
+        None => panic!("no source text available for {}", fm.name),
+    };
+    // `line_bounds` includes the line's trailing newline.  Strip it so that a column past the
+    // end-of-line position is rejected rather than resolving into the line ending or the next line.
+    let line_src = full_line_src.trim_end_matches(|c| c == '\n' || c == '\r');
+
+    let byte_off = match col_to_byte_offset(line_src, encoding, col) {
+        Some(off) => off,
+        None => panic!("column {} is outside the bounds of {} line {}", col, fm.name, line),
+    };
+
+    lo + BytePos(byte_off as u32)
+}
+
+#[cfg(test)]
+mod column_tests {
+    use super::{col_to_byte_offset, ColumnEncoding};
+
+    #[test]
+    fn utf8_column_is_a_byte_offset() {
+        assert_eq!(col_to_byte_offset("abc", ColumnEncoding::Utf8, 0), Some(0));
+        assert_eq!(col_to_byte_offset("abc", ColumnEncoding::Utf8, 2), Some(2));
+        // col == line length is the valid end-of-line position.
+        assert_eq!(col_to_byte_offset("abc", ColumnEncoding::Utf8, 3), Some(3));
+    }
+
+    #[test]
+    fn column_past_end_of_line_is_out_of_range() {
+        assert_eq!(col_to_byte_offset("abc", ColumnEncoding::Utf8, 4), None);
+        assert_eq!(col_to_byte_offset("", ColumnEncoding::Utf8, 1), None);
+    }
+
+    #[test]
+    fn utf16_mid_surrogate_column_clamps_to_char_start() {
+        // U+1F600 is one char, 4 UTF-8 bytes, 2 UTF-16 code units (a surrogate pair).
+        let line = "a\u{1F600}b";
+        // col 1 is the emoji's first code unit: its start.
+        assert_eq!(col_to_byte_offset(line, ColumnEncoding::Utf16, 1), Some(1));
+        // col 2 is the emoji's second (low-surrogate) code unit; clamp back to its start rather
+        // than advancing past the whole char.
+        assert_eq!(col_to_byte_offset(line, ColumnEncoding::Utf16, 2), Some(1));
+        // col 3 is past the emoji, at the following 'b'.
+        assert_eq!(col_to_byte_offset(line, ColumnEncoding::Utf16, 3), Some(5));
+    }
+
+    #[test]
+    fn utf32_column_counts_chars_not_bytes() {
+        let line = "h\u{e9}llo"; // 'é' is 1 char but 2 UTF-8 bytes
+        assert_eq!(col_to_byte_offset(line, ColumnEncoding::Utf32, 1), Some(1));
+        assert_eq!(col_to_byte_offset(line, ColumnEncoding::Utf32, 2), Some(3));
+    }
+}
+
 pub fn pick_node_at_loc(krate: &Crate,
                         cx: &driver::Ctxt,
                         kind: NodeKind,
+                        encoding: ColumnEncoding,
                         file: &str,
                         line: u32,
                         col: u32) -> Option<NodeInfo> {
@@ -239,15 +884,8 @@ pub fn pick_node_at_loc(krate: &Crate,
     if line == 0 || line as usize - 1 >= fm.lines.borrow().len() {
         panic!("line {} is outside the bounds of {}", line, file);
     };
-    let (lo, hi) = fm.line_bounds(line as usize - 1);
-
-    let line_len = hi.0 - lo.0;
-    if col >= line_len {
-        panic!("column {} is outside the bounds of {} line {}", col, file, line);
-    }
 
-    // TODO: make this work when the line contains multibyte characters
-    let pos = lo + BytePos(col);
+    let pos = line_col_to_byte_pos(&fm, encoding, line, col);
 
     pick_node(krate, kind, pos)
 }
@@ -257,8 +895,14 @@ pub fn pick_node_command(krate: &Crate, cx: &driver::Ctxt, args: &[String]) {
     let file = &args[1];
     let line = u32::from_str(&args[2]).unwrap();
     let col = u32::from_str(&args[3]).unwrap();
+    let encoding = match args.get(4).map(|s| s.as_str()) {
+        Some("utf16") => ColumnEncoding::Utf16,
+        Some("utf32") => ColumnEncoding::Utf32,
+        Some("utf8") | None => ColumnEncoding::Utf8,
+        Some(other) => panic!("unknown column encoding {:?}", other),
+    };
 
-    let result = pick_node_at_loc(krate, cx, kind, file, line, col);
+    let result = pick_node_at_loc(krate, cx, kind, encoding, file, line, col);
 
     if let Some(ref result) = result {
         let lo_loc = cx.session().codemap().lookup_char_pos(result.span.lo);
@@ -273,3 +917,333 @@ pub fn pick_node_command(krate: &Crate, cx: &driver::Ctxt, args: &[String]) {
         info!("{{ found: false }}");
     }
 }
+
+
+/// One segment of a path as returned by `find_ref_path`.  Callers splice these into a `use` item
+/// or a qualified path expression to name the target item from elsewhere in the crate.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathSegmentKind {
+    Super,
+    Crate,
+    Ident(Ident),
+}
+
+/// Per-module bookkeeping built by `ModuleMapVisitor`: the module's parent (`None` for the crate
+/// root), the items it directly defines, and the non-glob names it re-exports via `use`.
+struct ModuleInfo {
+    parent: Option<NodeId>,
+    items: Vec<(Ident, NodeId)>,
+    // (local name, name of the item referred to by the last segment of the `use` path).
+    // Resolving the full path would require real name resolution, which this syntax-only pass
+    // doesn't have, so re-exports are matched by name alone; see `find_path_to`'s
+    // `unique_name_to_id`.
+    reexports: Vec<(Ident, Ident)>,
+}
+
+/// Walks the crate recording its module nesting (`mod` items) and each module's re-exports, so
+/// that `find_ref_path` can search the module tree without needing a real resolver.
+struct ModuleMapVisitor {
+    modules: HashMap<NodeId, ModuleInfo>,
+    item_names: HashMap<NodeId, Ident>,
+    stack: Vec<NodeId>,
+}
+
+impl<'a> Visitor<'a> for ModuleMapVisitor {
+    fn visit_item(&mut self, x: &'a Item) {
+        let cur = *self.stack.last().unwrap();
+        self.item_names.insert(x.id, x.ident);
+        if let Some(info) = self.modules.get_mut(&cur) {
+            info.items.push((x.ident, x.id));
+        }
+
+        match x.node {
+            ItemKind::Mod(_) => {
+                self.modules.entry(x.id).or_insert_with(|| ModuleInfo {
+                    parent: Some(cur),
+                    items: Vec::new(),
+                    reexports: Vec::new(),
+                });
+                self.stack.push(x.id);
+                visit::walk_item(self, x);
+                self.stack.pop();
+            }
+
+            ItemKind::Use(ref vp) => {
+                match vp.node {
+                    ViewPath_::ViewPathSimple(rename, ref path) => {
+                        if let Some(seg) = path.segments.last() {
+                            if let Some(info) = self.modules.get_mut(&cur) {
+                                info.reexports.push((rename, seg.identifier));
+                            }
+                        }
+                    }
+                    ViewPath_::ViewPathList(_, ref items) => {
+                        for item in items {
+                            let local = item.node.rename.unwrap_or(item.node.name);
+                            if let Some(info) = self.modules.get_mut(&cur) {
+                                info.reexports.push((local, item.node.name));
+                            }
+                        }
+                    }
+                    ViewPath_::ViewPathGlob(_) => {
+                        // Not tracked: resolving a glob re-export needs real name resolution.
+                    }
+                }
+            }
+
+            // Other item kinds (fns, impls, ...) don't introduce modules or re-exports of their
+            // own, but a `mod` can still be nested inside one (e.g. a `mod` declared inside a
+            // function body), so recurse to pick those up too.  `x` is the current scope while
+            // doing so: items and `use`s nested in it aren't defined directly in the enclosing
+            // module, and `x` has no `ModuleInfo`, so they aren't recorded in any module.
+            _ => {
+                self.stack.push(x.id);
+                visit::walk_item(self, x);
+                self.stack.pop();
+            }
+        }
+    }
+
+    fn visit_mac(&mut self, mac: &'a Mac) {
+        visit::walk_mac(self, mac);
+    }
+}
+
+/// Relax `id`'s tentative cost/path if `cost` beats whatever's currently recorded.  Shared by
+/// every edge `find_path_to`'s Dijkstra search relaxes, so all of them go through the same
+/// "is this better than what we've already found" check.
+fn relax(dist: &mut HashMap<NodeId, usize>,
+        path_to: &mut HashMap<NodeId, Vec<PathSegmentKind>>,
+        heap: &mut BinaryHeap<Reverse<(usize, NodeId)>>,
+        id: NodeId,
+        cost: usize,
+        path: Vec<PathSegmentKind>) {
+    if dist.get(&id).map_or(true, |&d| cost < d) {
+        dist.insert(id, cost);
+        path_to.insert(id, path);
+        heap.push(Reverse((cost, id)));
+    }
+}
+
+/// Core of `find_ref_path`: find the shortest syntactic path from `from_id` to `target_id` via a
+/// bounded Dijkstra search over the module tree (modeled on rust-analyzer's `find_path`), rather
+/// than a DFS, so the path returned is the global shortest rather than the best of whichever
+/// branches a DFS happened to explore first.  `budget` bounds the search to paths of at most that
+/// many segments.
+///
+/// Costs are in path segments.  `from_id` starts the frontier at cost 0 (its own items are
+/// nameable by their bare name); each ancestor of `from_id` is seeded at the cost of the
+/// `super`/`crate` prefix that names it.  From there, every item directly defined in a
+/// frontier module is reachable one segment further out, under its own name; re-exports are
+/// reachable the same way, but (since this is a syntax-only pass with no real name resolution)
+/// only when the re-exported name is unique crate-wide — `unique_name_to_id` resolves the
+/// re-export's name to a `NodeId` only in that case, so an ambiguous same-name collision is simply
+/// not traversable, rather than risking a path that resolves to the wrong item.
+///
+/// See `find_ref_path` for the scope limitation (no external-crate/prelude awareness) that this
+/// inherits.
+fn find_path_to(modules: &HashMap<NodeId, ModuleInfo>,
+                item_names: &HashMap<NodeId, Ident>,
+                target_id: NodeId,
+                from_id: NodeId,
+                budget: usize) -> Option<Vec<PathSegmentKind>> {
+    let mut name_counts: HashMap<Name, u32> = HashMap::new();
+    for ident in item_names.values() {
+        *name_counts.entry(ident.name).or_insert(0) += 1;
+    }
+    let mut unique_name_to_id: HashMap<Name, NodeId> = HashMap::new();
+    for (&id, ident) in item_names.iter() {
+        if name_counts.get(&ident.name) == Some(&1) {
+            unique_name_to_id.insert(ident.name, id);
+        }
+    }
+
+    let mut dist: HashMap<NodeId, usize> = HashMap::new();
+    let mut path_to: HashMap<NodeId, Vec<PathSegmentKind>> = HashMap::new();
+    let mut heap = BinaryHeap::new();
+
+    relax(&mut dist, &mut path_to, &mut heap, from_id, 0, Vec::new());
+
+    // Seed the `super`/`crate` prefixes relative to `from_id`.
+    let mut ancestor = from_id;
+    let mut depth = 1;
+    while let Some(parent) = modules.get(&ancestor).and_then(|info| info.parent) {
+        let segs = if parent == CRATE_NODE_ID {
+            vec![PathSegmentKind::Crate]
+        } else {
+            vec![PathSegmentKind::Super; depth]
+        };
+        relax(&mut dist, &mut path_to, &mut heap, parent, segs.len(), segs);
+        ancestor = parent;
+        depth += 1;
+    }
+
+    while let Some(Reverse((cost, mod_id))) = heap.pop() {
+        if cost > budget {
+            break;
+        }
+        if dist.get(&mod_id).map_or(false, |&d| cost > d) {
+            continue; // a cheaper path to `mod_id` was already relaxed
+        }
+        if mod_id == target_id {
+            return path_to.get(&target_id).cloned();
+        }
+        let info = match modules.get(&mod_id) {
+            Some(info) => info,
+            None => continue,
+        };
+        let next_cost = cost + 1;
+        if next_cost > budget {
+            continue;
+        }
+        let prefix = path_to[&mod_id].clone();
+        for &(name, id) in &info.items {
+            let mut path = prefix.clone();
+            path.push(PathSegmentKind::Ident(name));
+            relax(&mut dist, &mut path_to, &mut heap, id, next_cost, path);
+        }
+        for &(local, orig) in &info.reexports {
+            if let Some(&id) = unique_name_to_id.get(&orig.name) {
+                let mut path = prefix.clone();
+                path.push(PathSegmentKind::Ident(local));
+                relax(&mut dist, &mut path_to, &mut heap, id, next_cost, path);
+            }
+        }
+    }
+
+    // `target_id` was never reached within `budget` segments.
+    None
+}
+
+/// Find the shortest path that refers to `item_id` (as picked via `NodeKind::Item`) from
+/// `from_id`'s module, suitable for splicing into a `use` item or a qualified reference.
+///
+/// Known gap: this only resolves names within the current crate's own module tree.  Items from
+/// external crates (`core`, `alloc`, `std`, ...) aren't modeled at all, so unlike
+/// rust-analyzer's `find_path`, there's no tie-break preferring non-`core`/`alloc` and
+/// prelude-reachable names among candidate paths -- that would need a real resolver (or at least
+/// a crate-name blocklist) that this syntax-only pass doesn't have. Deliberately descoped rather
+/// than attempted partially.
+pub fn find_ref_path(krate: &Crate,
+                     _cx: &driver::Ctxt,
+                     item_id: NodeId,
+                     from_id: NodeId) -> Option<Vec<PathSegmentKind>> {
+    let mut v = ModuleMapVisitor {
+        modules: HashMap::new(),
+        item_names: HashMap::new(),
+        stack: vec![CRATE_NODE_ID],
+    };
+    v.modules.insert(CRATE_NODE_ID, ModuleInfo { parent: None, items: Vec::new(), reexports: Vec::new() });
+    krate.visit(&mut v);
+
+    if item_id == from_id {
+        return Some(Vec::new());
+    }
+
+    find_path_to(&v.modules, &v.item_names, item_id, from_id, 15)
+}
+
+#[cfg(test)]
+mod find_path_tests {
+    use super::*;
+
+    fn module(parent: Option<NodeId>, items: Vec<(Ident, NodeId)>, reexports: Vec<(Ident, Ident)>) -> ModuleInfo {
+        ModuleInfo { parent: parent, items: items, reexports: reexports }
+    }
+
+    #[test]
+    fn direct_reexport_beats_the_longer_super_path_despite_a_reexport_cycle() {
+        // crate
+        //   mod a { fn target() {} }      -- target lives here
+        //   mod b { pub use a::target; pub use b_helper::helper; }
+        //   mod b_helper { pub use b::unrelated as helper; fn unrelated() {} }
+        //
+        // `b` and `b_helper` re-export from each other, forming a cycle that a naive recursive
+        // search could loop on forever; Dijkstra's cost tracking must still terminate and prefer
+        // the single-segment path through `b`'s own re-export over the longer path up through
+        // `b`'s parent (`super::a::target`, which `find_path_to` spells `crate::a::target` since
+        // that parent is the crate root).
+        let mod_a = NodeId::new(1);
+        let mod_b = NodeId::new(2);
+        let mod_b_helper = NodeId::new(3);
+        let target = NodeId::new(100);
+        let unrelated = NodeId::new(101);
+
+        let mut item_names = HashMap::new();
+        item_names.insert(target, Ident::from_str("target"));
+        item_names.insert(unrelated, Ident::from_str("unrelated"));
+
+        let mut modules = HashMap::new();
+        modules.insert(CRATE_NODE_ID, module(None,
+            vec![(Ident::from_str("a"), mod_a), (Ident::from_str("b"), mod_b),
+                 (Ident::from_str("b_helper"), mod_b_helper)],
+            vec![]));
+        modules.insert(mod_a, module(Some(CRATE_NODE_ID), vec![(Ident::from_str("target"), target)], vec![]));
+        modules.insert(mod_b, module(Some(CRATE_NODE_ID), vec![],
+            vec![(Ident::from_str("target"), Ident::from_str("target")),
+                 (Ident::from_str("helper"), Ident::from_str("helper"))]));
+        modules.insert(mod_b_helper, module(Some(CRATE_NODE_ID),
+            vec![(Ident::from_str("unrelated"), unrelated)],
+            vec![(Ident::from_str("helper"), Ident::from_str("unrelated"))]));
+
+        let path = find_path_to(&modules, &item_names, target, mod_b, 15).unwrap();
+        assert_eq!(path, vec![PathSegmentKind::Ident(Ident::from_str("target"))]);
+    }
+
+    #[test]
+    fn reexport_path_uses_the_local_alias_not_the_original_name() {
+        // crate
+        //   mod a { fn target() {} }
+        //   mod b { pub use a::target as renamed; }
+        //
+        // `target` is only reachable from `b` through the re-export, and under its alias
+        // `renamed` -- the returned path must name it `renamed`, not `target` (the regression
+        // fixed in d923702).
+        let mod_a = NodeId::new(1);
+        let mod_b = NodeId::new(2);
+        let target = NodeId::new(100);
+
+        let mut item_names = HashMap::new();
+        item_names.insert(target, Ident::from_str("target"));
+
+        let mut modules = HashMap::new();
+        modules.insert(CRATE_NODE_ID, module(None, vec![], vec![]));
+        modules.insert(mod_a, module(Some(CRATE_NODE_ID), vec![(Ident::from_str("target"), target)], vec![]));
+        modules.insert(mod_b, module(Some(CRATE_NODE_ID), vec![],
+            vec![(Ident::from_str("renamed"), Ident::from_str("target"))]));
+
+        let path = find_path_to(&modules, &item_names, target, mod_b, 15).unwrap();
+        assert_eq!(path, vec![PathSegmentKind::Ident(Ident::from_str("renamed"))]);
+    }
+
+    #[test]
+    fn ambiguous_reexport_name_is_not_traversable() {
+        // Two unrelated items both happen to be named `target`; a module re-exporting something
+        // named `target` can't be resolved back to either one without real name resolution, so
+        // `find_path_to` must not treat that re-export as a usable edge, even though it would
+        // otherwise be the shortest path.
+        let mod_a = NodeId::new(1);
+        let mod_b = NodeId::new(2);
+        let mod_c = NodeId::new(3);
+        let target = NodeId::new(100);
+        let decoy = NodeId::new(101);
+
+        let mut item_names = HashMap::new();
+        item_names.insert(target, Ident::from_str("target"));
+        item_names.insert(decoy, Ident::from_str("target"));
+
+        let mut modules = HashMap::new();
+        modules.insert(CRATE_NODE_ID, module(None,
+            vec![(Ident::from_str("a"), mod_a), (Ident::from_str("b"), mod_b), (Ident::from_str("c"), mod_c)],
+            vec![]));
+        modules.insert(mod_a, module(Some(CRATE_NODE_ID), vec![(Ident::from_str("target"), target)], vec![]));
+        modules.insert(mod_b, module(Some(CRATE_NODE_ID), vec![(Ident::from_str("target"), decoy)], vec![]));
+        modules.insert(mod_c, module(Some(CRATE_NODE_ID), vec![],
+            vec![(Ident::from_str("target"), Ident::from_str("target"))]));
+
+        let path = find_path_to(&modules, &item_names, target, mod_c, 15).unwrap();
+        // Must fall back to the real (longer) path via `crate::a`, not the ambiguous re-export.
+        assert_eq!(path, vec![PathSegmentKind::Crate, PathSegmentKind::Ident(Ident::from_str("a")),
+                               PathSegmentKind::Ident(Ident::from_str("target"))]);
+    }
+}